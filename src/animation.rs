@@ -0,0 +1,274 @@
+//! Deep-zoom animation mode: instead of one `mandelbrot.csv`, renders a
+//! sequence of frames that zoom toward a target point. Each frame is chunked
+//! the same way [`crate::run_local`] chunks a single render: workers generate
+//! points from their own chunk `Grid` slice (via [`cluster::split_into_chunks`])
+//! instead of the whole frame being materialized up front.
+
+use std::{
+    collections::VecDeque,
+    sync::{mpsc::channel, Arc},
+    time::SystemTime,
+};
+
+use async_compression::{tokio::write::ZstdEncoder, Level};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    io::AsyncWriteExt,
+    sync::{mpsc, Mutex},
+    task::JoinHandle,
+};
+
+use crate::{
+    cluster, count_grid_points, generate_grid_points, mandelbrot_result, Config, Grid,
+    OutputFormat, Point, PointRecord, CHUNK_SIZE, WRITER_CHANNEL_CAPACITY,
+};
+
+/// Zoom parameters for the animation [`RunMode`](crate::RunMode). Each frame
+/// shrinks the base grid around `(center_re, center_im)` by `zoom_factor`
+/// relative to the previous one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct Animation {
+    pub(crate) center_re: f64,
+    pub(crate) center_im: f64,
+    pub(crate) zoom_factor: f64,
+    pub(crate) frames: usize,
+}
+
+/// Shrinks `base` around the animation's center by `zoom_factor` raised to
+/// `frame_index`, scaling `delta` the same way so every frame keeps roughly
+/// the same point density.
+fn zoomed_grid(base: &Grid, animation: &Animation, frame_index: usize) -> Grid {
+    let scale = animation.zoom_factor.powi(frame_index as i32);
+    let half_re = (base.re_max - base.re_min) / 2.0 * scale;
+    let half_im = (base.im_max - base.im_min) / 2.0 * scale;
+
+    Grid {
+        re_min: animation.center_re - half_re,
+        re_max: animation.center_re + half_re,
+        im_min: animation.center_im - half_im,
+        im_max: animation.center_im + half_im,
+        delta: base.delta * scale,
+    }
+}
+
+fn frame_filename(output: OutputFormat, frame_index: usize) -> String {
+    match output {
+        OutputFormat::Csv => format!("mandelbrot_frame_{frame_index:04}.csv"),
+        OutputFormat::Bincode { .. } => format!("mandelbrot_frame_{frame_index:04}.bin.zst"),
+    }
+}
+
+/// A single frame's output file, opened lazily by [`run_frame_writer`] the
+/// moment its first chunk arrives.
+enum FrameSink {
+    Csv(csv::Writer<std::fs::File>),
+    Bincode(ZstdEncoder<tokio::fs::File>),
+}
+
+impl FrameSink {
+    async fn create(filename: String, output: OutputFormat) -> anyhow::Result<Self> {
+        match output {
+            OutputFormat::Csv => {
+                let mut wtr = csv::Writer::from_path(filename)?;
+                wtr.write_record(["re", "im", "value"])?;
+                Ok(FrameSink::Csv(wtr))
+            }
+            OutputFormat::Bincode { level } => {
+                let file = tokio::fs::File::create(filename).await?;
+                Ok(FrameSink::Bincode(ZstdEncoder::with_quality(
+                    file,
+                    Level::Precise(level),
+                )))
+            }
+        }
+    }
+
+    async fn write_chunk(&mut self, chunk: &[Point]) -> anyhow::Result<()> {
+        match self {
+            FrameSink::Csv(wtr) => {
+                for p in chunk {
+                    wtr.serialize((p.position.re(), p.position.im(), p.value))?;
+                }
+            }
+            FrameSink::Bincode(encoder) => {
+                for p in chunk {
+                    let record = PointRecord::from(p);
+                    encoder.write_all(&bincode::serialize(&record)?).await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn finish(self) -> anyhow::Result<()> {
+        match self {
+            FrameSink::Csv(mut wtr) => wtr.flush().map_err(anyhow::Error::new),
+            FrameSink::Bincode(mut encoder) => encoder.shutdown().await.map_err(anyhow::Error::new),
+        }
+    }
+}
+
+/// Drains `(frame_index, chunk_index, points)` tuples and writes them to
+/// numbered per-frame files, opening the next file as soon as a new
+/// `frame_index` shows up and closing the previous one. The buffer pool is
+/// shared across the whole animation, not just within a frame.
+async fn run_frame_writer(
+    output: OutputFormat,
+    frame_count: usize,
+    mut data_rx: mpsc::Receiver<(usize, usize, Vec<Point>)>,
+    return_tx: mpsc::Sender<Vec<Point>>,
+) -> anyhow::Result<usize> {
+    let mut points_written = 0;
+    let mut current_frame: Option<(usize, FrameSink)> = None;
+
+    while let Some((frame_index, _chunk_index, mut chunk)) = data_rx.recv().await {
+        if current_frame.as_ref().map(|(f, _)| *f) != Some(frame_index) {
+            if let Some((_, sink)) = current_frame.take() {
+                sink.finish().await?;
+            }
+            println!("writing frame {}/{frame_count}", frame_index + 1);
+            let sink = FrameSink::create(frame_filename(output, frame_index), output).await?;
+            current_frame = Some((frame_index, sink));
+        }
+
+        if let Some((_, sink)) = current_frame.as_mut() {
+            sink.write_chunk(&chunk).await?;
+        }
+        points_written += chunk.len();
+
+        chunk.clear();
+        let _ = return_tx.send(chunk).await;
+    }
+
+    if let Some((_, sink)) = current_frame.take() {
+        sink.finish().await?;
+    }
+
+    Ok(points_written)
+}
+
+/// Renders `animation.frames` frames of `config.grid`, zooming toward
+/// `animation`'s center one step per frame. Each frame runs through the same
+/// chunk-queue-and-buffer-pool pipeline as [`crate::run_local`] - chunks are
+/// sub-`Grid` bounds, not pre-generated points, so a frame's points are never
+/// all resident in memory at once - but the buffer pool and writer task live
+/// for the whole animation instead of a single frame.
+pub(crate) async fn run_animation(config: Config, animation: Animation) -> anyhow::Result<()> {
+    let start = SystemTime::now();
+
+    let (data_tx, data_rx) = mpsc::channel::<(usize, usize, Vec<Point>)>(WRITER_CHANNEL_CAPACITY);
+    let (return_tx, return_rx) =
+        mpsc::channel::<Vec<Point>>(config.threads + WRITER_CHANNEL_CAPACITY);
+    for _ in 0..(config.threads + WRITER_CHANNEL_CAPACITY) {
+        return_tx
+            .send(Vec::with_capacity(CHUNK_SIZE))
+            .await
+            .expect("seeding buffer pool");
+    }
+    let return_rx = Arc::new(Mutex::new(return_rx));
+
+    let writer_handle: JoinHandle<anyhow::Result<usize>> = tokio::spawn(run_frame_writer(
+        config.output,
+        animation.frames,
+        data_rx,
+        return_tx,
+    ));
+
+    for frame_index in 0..animation.frames {
+        let grid = zoomed_grid(&config.grid, &animation, frame_index);
+        if grid.delta <= 0.0 {
+            anyhow::bail!(
+                "frame {frame_index} shrank delta to {}, which is too deep a zoom for f64 precision",
+                grid.delta
+            );
+        }
+        let total_work_amount = count_grid_points(&grid);
+        let chunk_count = ((total_work_amount + CHUNK_SIZE - 1) / CHUNK_SIZE).max(1);
+
+        let pending: VecDeque<(usize, Grid)> = cluster::split_into_chunks(&grid, chunk_count)
+            .into_iter()
+            .enumerate()
+            .collect();
+        let pending = Arc::new(Mutex::new(pending));
+
+        let (progress_sender, progress_receiver) = channel::<usize>();
+        let mut join_handles = vec![];
+
+        for _ in 0..config.threads {
+            let sender = progress_sender.clone();
+            let cfg = config.clone();
+            let data_tx = data_tx.clone();
+            let return_rx = Arc::clone(&return_rx);
+            let pending = Arc::clone(&pending);
+
+            join_handles.push(tokio::spawn(async move {
+                loop {
+                    let (chunk_index, grid) = match pending.lock().await.pop_front() {
+                        Some(item) => item,
+                        None => break,
+                    };
+                    let points = generate_grid_points(&grid);
+
+                    let mut buffer = return_rx
+                        .lock()
+                        .await
+                        .recv()
+                        .await
+                        .expect("buffer pool closed while a worker still had work");
+                    buffer.clear();
+
+                    let mut progress_since_report = 0;
+                    for point in points {
+                        buffer.push(mandelbrot_result(point, cfg.iterations, cfg.bound));
+                        progress_since_report += 1;
+                        if progress_since_report == 1000 {
+                            sender
+                                .send(progress_since_report)
+                                .expect("Could send progress");
+                            progress_since_report = 0;
+                        }
+                    }
+                    if progress_since_report > 0 {
+                        sender
+                            .send(progress_since_report)
+                            .expect("Could send progress");
+                    }
+
+                    data_tx
+                        .send((frame_index, chunk_index, buffer))
+                        .await
+                        .expect("writer task gone");
+                }
+            }));
+        }
+        drop(progress_sender);
+
+        let mut total_progress = 0;
+        while let Ok(single_progress) = progress_receiver.recv() {
+            total_progress += single_progress;
+            let progress_percentage =
+                (total_progress as f64 / total_work_amount.max(1) as f64) * 100_f64;
+            print!(
+                "\rframe {}/{}: {progress_percentage:.2}%      ",
+                frame_index + 1,
+                animation.frames
+            );
+        }
+
+        for handle in join_handles {
+            handle.await.expect("could not join thread");
+        }
+    }
+    println!();
+
+    drop(data_tx);
+    let points_written = writer_handle.await.expect("could not join writer task")?;
+    println!(
+        "animation done, wrote {points_written} points across {} frames",
+        animation.frames
+    );
+
+    let duration = SystemTime::now().duration_since(start)?;
+    println!("took: {}ms", duration.as_millis());
+    Ok(())
+}
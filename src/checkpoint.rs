@@ -0,0 +1,178 @@
+//! Checkpointing for resumable local renders. Completed chunks are appended,
+//! zstd-compressed, to a sidecar file keyed by a `blake3` hash of the
+//! `Config` fields that decide the computed values (grid, iterations, bound),
+//! so a changed grid, iteration count, or bound naturally picks a different
+//! file instead of reusing stale results, while e.g. a changed `threads`
+//! keeps the same one.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use async_compression::{
+    tokio::{bufread::ZstdDecoder, write::ZstdEncoder},
+    Level,
+};
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncReadExt, AsyncWriteExt, BufReader};
+
+use crate::{Config, PointRecord};
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CheckpointEntry {
+    chunk_index: usize,
+    points: Vec<PointRecord>,
+}
+
+/// Derives the sidecar path for `config` from its grid, iterations and bound.
+pub(crate) fn checkpoint_path(config: &Config) -> anyhow::Result<PathBuf> {
+    let bytes = bincode::serialize(&(&config.grid, config.iterations, config.bound))?;
+    let hash = blake3::hash(&bytes);
+    Ok(PathBuf::from(format!(
+        "mandelbrot-{}.checkpoint.zst",
+        hash.to_hex()
+    )))
+}
+
+/// Loads chunks completed by a previous run, if a checkpoint for this exact
+/// `Config` exists. Returns an empty map on first run or once the config has
+/// changed enough to pick a different path. A partial entry left over from a
+/// run that crashed mid-write is silently dropped.
+pub(crate) async fn load(path: &Path) -> anyhow::Result<HashMap<usize, Vec<PointRecord>>> {
+    if !path.exists() {
+        return Ok(HashMap::new());
+    }
+
+    let file = tokio::fs::File::open(path).await?;
+    let mut decoder = ZstdDecoder::new(BufReader::new(file));
+    let mut bytes = Vec::new();
+    decoder.read_to_end(&mut bytes).await?;
+
+    let mut entries = HashMap::new();
+    let mut cursor = &bytes[..];
+    while cursor.len() >= 8 {
+        let (len_bytes, rest) = cursor.split_at(8);
+        let len = u64::from_be_bytes(len_bytes.try_into().unwrap()) as usize;
+        if rest.len() < len {
+            break;
+        }
+        let (entry_bytes, rest) = rest.split_at(len);
+        let entry: CheckpointEntry = bincode::deserialize(entry_bytes)?;
+        entries.insert(entry.chunk_index, entry.points);
+        cursor = rest;
+    }
+
+    Ok(entries)
+}
+
+/// Appends completed chunks to the checkpoint sidecar as they arrive,
+/// flushing after each one so a crash only ever loses the in-flight chunk.
+/// Writes go to a `.tmp` file that only replaces the real sidecar once
+/// [`Self::finish`] renames it into place, so a crash during the replay of
+/// previously-completed chunks can never wipe out the old, still-valid
+/// checkpoint.
+pub(crate) struct CheckpointWriter {
+    encoder: ZstdEncoder<tokio::fs::File>,
+    tmp_path: PathBuf,
+    final_path: PathBuf,
+}
+
+impl CheckpointWriter {
+    /// Creates the `.tmp` sidecar that `path` will become once [`Self::finish`]
+    /// renames it in. The caller is expected to replay any chunks returned by
+    /// [`load`] through [`Self::append`] right after creation, since this
+    /// run's file starts out empty; until `finish` runs, `path` itself is
+    /// untouched.
+    pub(crate) async fn create(path: &Path) -> anyhow::Result<Self> {
+        let tmp_path = PathBuf::from(format!("{}.tmp", path.display()));
+        let file = tokio::fs::File::create(&tmp_path).await?;
+        Ok(Self {
+            encoder: ZstdEncoder::with_quality(file, Level::Default),
+            tmp_path,
+            final_path: path.to_path_buf(),
+        })
+    }
+
+    pub(crate) async fn append(
+        &mut self,
+        chunk_index: usize,
+        points: &[PointRecord],
+    ) -> anyhow::Result<()> {
+        let entry = CheckpointEntry {
+            chunk_index,
+            points: points.to_vec(),
+        };
+        let payload = bincode::serialize(&entry)?;
+        self.encoder
+            .write_all(&(payload.len() as u64).to_be_bytes())
+            .await?;
+        self.encoder.write_all(&payload).await?;
+        self.encoder.flush().await?;
+        Ok(())
+    }
+
+    /// Flushes the `.tmp` file and atomically renames it over the real
+    /// sidecar - the only point at which the previous checkpoint's content
+    /// can be lost, and only once everything it held is safely replayed into
+    /// the new file.
+    pub(crate) async fn finish(mut self) -> anyhow::Result<()> {
+        self.encoder.shutdown().await?;
+        tokio::fs::rename(&self.tmp_path, &self.final_path).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn writer_and_loader_round_trip_chunks() {
+        let path = std::env::temp_dir().join(format!(
+            "mandelbrot-checkpoint-roundtrip-{}.checkpoint.zst",
+            std::process::id()
+        ));
+
+        let chunk_0 = vec![PointRecord {
+            re: 0.0,
+            im: 0.0,
+            value: 1.0,
+        }];
+        let chunk_1 = vec![
+            PointRecord {
+                re: 0.1,
+                im: 0.2,
+                value: 2.0,
+            },
+            PointRecord {
+                re: 0.3,
+                im: 0.4,
+                value: 3.0,
+            },
+        ];
+
+        let mut writer = CheckpointWriter::create(&path).await.unwrap();
+        writer.append(0, &chunk_0).await.unwrap();
+        writer.append(1, &chunk_1).await.unwrap();
+        writer.finish().await.unwrap();
+
+        let loaded = load(&path).await.unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded[&0], chunk_0);
+        assert_eq!(loaded[&1], chunk_1);
+    }
+
+    #[tokio::test]
+    async fn load_returns_empty_map_when_no_checkpoint_exists() {
+        let path = std::env::temp_dir().join(format!(
+            "mandelbrot-checkpoint-missing-{}.checkpoint.zst",
+            std::process::id()
+        ));
+
+        let loaded = load(&path).await.unwrap();
+        assert!(loaded.is_empty());
+    }
+}
@@ -0,0 +1,329 @@
+//! Distributed compute mode: a coordinator hands out grid slices to workers
+//! connected over TCP instead of spawning local threads. Each worker gets
+//! only a [`Grid`] slice (re/im bounds + delta), regenerates its own points
+//! with [`generate_grid_points`], and streams the results back - the point
+//! list is never sent to workers; only the computed points come back.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+};
+
+use num::Complex;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    sync::{mpsc, Mutex},
+};
+
+use crate::{
+    generate_grid_points, mandelbrot_result, run_writer, Config, Grid, OutputFormat, Point,
+    PointRecord, WRITER_CHANNEL_CAPACITY,
+};
+
+/// How many times a chunk is handed to a different worker before the
+/// coordinator gives up on it.
+const MAX_CHUNK_RETRIES: usize = 3;
+
+/// A slice of the overall grid, self-contained enough for a worker to
+/// regenerate its own points instead of receiving the full point list.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct WorkItem {
+    chunk_index: usize,
+    grid: Grid,
+    iterations: usize,
+    bound: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ChunkResult {
+    chunk_index: usize,
+    points: Vec<PointRecord>,
+}
+
+async fn send_message<T: Serialize>(stream: &mut TcpStream, message: &T) -> anyhow::Result<()> {
+    let payload = bincode::serialize(message)?;
+    stream
+        .write_all(&(payload.len() as u32).to_be_bytes())
+        .await?;
+    stream.write_all(&payload).await?;
+    Ok(())
+}
+
+async fn recv_message<T: DeserializeOwned>(stream: &mut TcpStream) -> anyhow::Result<T> {
+    let mut len_buf = [0u8; 4];
+    stream.read_exact(&mut len_buf).await?;
+    let mut payload = vec![0u8; u32::from_be_bytes(len_buf) as usize];
+    stream.read_exact(&mut payload).await?;
+    bincode::deserialize(&payload).map_err(anyhow::Error::new)
+}
+
+/// Splits `grid` into `chunk_count` horizontal bands along the imaginary
+/// axis. Also used by [`crate::run_local`] to derive chunk bounds without
+/// materializing the whole grid up front.
+pub(crate) fn split_into_chunks(grid: &Grid, chunk_count: usize) -> Vec<Grid> {
+    let band_height = (grid.im_max - grid.im_min) / chunk_count as f64;
+    (0..chunk_count)
+        .map(|i| Grid {
+            im_min: grid.im_min + band_height * i as f64,
+            im_max: grid.im_min + band_height * (i + 1) as f64,
+            ..grid.clone()
+        })
+        .collect()
+}
+
+/// Binds `bind_addr` and serves [`WorkItem`]s to connecting workers until
+/// every chunk has been computed, merging the results through the same
+/// writer task the local mode uses (without its buffer pool).
+pub(crate) async fn run_coordinator(
+    config: Config,
+    bind_addr: String,
+    expected_workers: usize,
+) -> anyhow::Result<()> {
+    let listener = TcpListener::bind(&bind_addr).await?;
+    println!("coordinator listening on {bind_addr}, waiting for workers...");
+
+    let chunks = split_into_chunks(&config.grid, expected_workers.max(1) * 4);
+    let total_chunks = chunks.len();
+    let pending: VecDeque<WorkItem> = chunks
+        .into_iter()
+        .enumerate()
+        .map(|(chunk_index, grid)| WorkItem {
+            chunk_index,
+            grid,
+            iterations: config.iterations,
+            bound: config.bound,
+        })
+        .collect();
+    let pending = Arc::new(Mutex::new(pending));
+    let retries = Arc::new(Mutex::new(HashMap::<usize, usize>::new()));
+    let failed_chunks = Arc::new(Mutex::new(Vec::<usize>::new()));
+
+    let filename = match config.output {
+        OutputFormat::Csv => "mandelbrot.csv".to_string(),
+        OutputFormat::Bincode { .. } => "mandelbrot.bin.zst".to_string(),
+    };
+    let (data_tx, data_rx) = mpsc::channel::<(usize, Vec<Point>)>(WRITER_CHANNEL_CAPACITY);
+    // The coordinator hands chunk results straight to the writer instead of
+    // round-tripping through a reusable buffer pool (network chunks already
+    // bound memory), so the return channel is just closed immediately.
+    let (return_tx, return_rx) = mpsc::channel::<Vec<Point>>(1);
+    drop(return_rx);
+    let writer_handle = tokio::spawn(run_writer(
+        filename,
+        config.output,
+        data_rx,
+        return_tx,
+        None,
+    ));
+
+    let (done_tx, mut done_rx) = mpsc::channel::<()>(total_chunks.max(1));
+
+    let accept_task = tokio::spawn({
+        let pending = Arc::clone(&pending);
+        let retries = Arc::clone(&retries);
+        let failed_chunks = Arc::clone(&failed_chunks);
+        async move {
+            loop {
+                let (socket, peer) = match listener.accept().await {
+                    Ok(accepted) => accepted,
+                    Err(e) => {
+                        eprintln!("accept failed: {e}");
+                        continue;
+                    }
+                };
+                println!("worker connected: {peer}");
+
+                let pending = Arc::clone(&pending);
+                let retries = Arc::clone(&retries);
+                let failed_chunks = Arc::clone(&failed_chunks);
+                let data_tx = data_tx.clone();
+                let done_tx = done_tx.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = handle_worker_connection(
+                        socket,
+                        pending,
+                        retries,
+                        failed_chunks,
+                        data_tx,
+                        done_tx,
+                    )
+                    .await
+                    {
+                        eprintln!("worker connection ended early: {e}");
+                    }
+                });
+            }
+        }
+    });
+
+    let mut chunks_done = 0;
+    while chunks_done < total_chunks {
+        done_rx
+            .recv()
+            .await
+            .ok_or_else(|| anyhow::anyhow!("all worker connections closed before finishing"))?;
+        chunks_done += 1;
+        let progress_percentage = (chunks_done as f64 / total_chunks as f64) * 100_f64;
+        print!("\r{progress_percentage:.2}%      ");
+    }
+    println!();
+    accept_task.abort();
+
+    let points_written = writer_handle.await.expect("could not join writer task")?;
+    let failed_chunks = failed_chunks.lock().await;
+    if failed_chunks.is_empty() {
+        println!("coordinator done, wrote {points_written} points");
+    } else {
+        println!(
+            "coordinator done, wrote {points_written} points ({} chunk(s) permanently failed and are missing from the output: {failed_chunks:?})",
+            failed_chunks.len()
+        );
+    }
+    Ok(())
+}
+
+/// Serves chunks to one worker connection until the pending queue is empty,
+/// requeueing its current chunk (up to [`MAX_CHUNK_RETRIES`] times) if the
+/// connection drops mid-chunk. A chunk that exceeds the retry budget is
+/// recorded in `failed_chunks` and still counted as "done" - otherwise the
+/// coordinator's main loop would wait forever for a done-signal that chunk
+/// can no longer produce.
+async fn handle_worker_connection(
+    mut socket: TcpStream,
+    pending: Arc<Mutex<VecDeque<WorkItem>>>,
+    retries: Arc<Mutex<HashMap<usize, usize>>>,
+    failed_chunks: Arc<Mutex<Vec<usize>>>,
+    data_tx: mpsc::Sender<(usize, Vec<Point>)>,
+    done_tx: mpsc::Sender<()>,
+) -> anyhow::Result<()> {
+    loop {
+        let work_item = match pending.lock().await.pop_front() {
+            Some(item) => item,
+            None => return Ok(()),
+        };
+        let chunk_index = work_item.chunk_index;
+
+        match dispatch_chunk(&mut socket, &work_item).await {
+            Ok(result) => {
+                let points = result
+                    .points
+                    .into_iter()
+                    .map(|r| Point {
+                        position: Complex::new(r.re, r.im),
+                        value: r.value,
+                    })
+                    .collect();
+                data_tx.send((chunk_index, points)).await?;
+                let _ = done_tx.send(()).await;
+            }
+            Err(e) => {
+                let mut retries_guard = retries.lock().await;
+                let attempts = retries_guard.entry(chunk_index).or_insert(0);
+                *attempts += 1;
+                if *attempts > MAX_CHUNK_RETRIES {
+                    eprintln!("chunk {chunk_index} failed {attempts} times, giving up: {e}");
+                    drop(retries_guard);
+                    failed_chunks.lock().await.push(chunk_index);
+                    let _ = done_tx.send(()).await;
+                } else {
+                    eprintln!(
+                        "worker disconnected mid-chunk {chunk_index} ({e}), reassigning (attempt {attempts})"
+                    );
+                    pending.lock().await.push_back(work_item);
+                }
+                return Err(e);
+            }
+        }
+    }
+}
+
+async fn dispatch_chunk(
+    socket: &mut TcpStream,
+    work_item: &WorkItem,
+) -> anyhow::Result<ChunkResult> {
+    send_message(socket, work_item).await?;
+    recv_message(socket).await
+}
+
+/// Connects to `coordinator_addr` and computes whatever chunks it sends
+/// until the coordinator closes the connection.
+pub(crate) async fn run_worker(coordinator_addr: String) -> anyhow::Result<()> {
+    let mut socket = TcpStream::connect(&coordinator_addr).await?;
+    println!("connected to coordinator at {coordinator_addr}");
+
+    loop {
+        let work_item: WorkItem = match recv_message(&mut socket).await {
+            Ok(item) => item,
+            Err(_) => {
+                println!("coordinator closed the connection, nothing left to do");
+                return Ok(());
+            }
+        };
+
+        println!("received chunk {}", work_item.chunk_index);
+        let points = generate_grid_points(&work_item.grid)
+            .into_iter()
+            .map(|p| {
+                PointRecord::from(&mandelbrot_result(p, work_item.iterations, work_item.bound))
+            })
+            .collect();
+
+        send_message(
+            &mut socket,
+            &ChunkResult {
+                chunk_index: work_item.chunk_index,
+                points,
+            },
+        )
+        .await?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_into_chunks_covers_the_grid_without_gaps_or_overlap() {
+        let grid = Grid {
+            re_min: -2.0,
+            re_max: 1.0,
+            im_min: -1.0,
+            im_max: 1.0,
+            delta: 0.01,
+        };
+
+        let chunks = split_into_chunks(&grid, 4);
+
+        assert_eq!(chunks.len(), 4);
+        assert_eq!(chunks.first().unwrap().im_min, grid.im_min);
+        assert_eq!(chunks.last().unwrap().im_max, grid.im_max);
+        for pair in chunks.windows(2) {
+            assert_eq!(pair[0].im_max, pair[1].im_min);
+        }
+        for chunk in &chunks {
+            assert_eq!(chunk.re_min, grid.re_min);
+            assert_eq!(chunk.re_max, grid.re_max);
+            assert_eq!(chunk.delta, grid.delta);
+        }
+    }
+
+    #[test]
+    fn split_into_chunks_one_chunk_is_the_whole_grid() {
+        let grid = Grid {
+            re_min: -2.0,
+            re_max: 1.0,
+            im_min: -1.0,
+            im_max: 1.0,
+            delta: 0.01,
+        };
+
+        let chunks = split_into_chunks(&grid, 1);
+
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].im_min, grid.im_min);
+        assert_eq!(chunks[0].im_max, grid.im_max);
+    }
+}
@@ -1,26 +1,49 @@
 use std::{
+    collections::VecDeque,
     fs::File,
     io::{Read, Write},
-    sync::mpsc::channel,
+    sync::{mpsc::channel, Arc},
     time::SystemTime,
 };
 
+use async_compression::{
+    tokio::{bufread::ZstdDecoder, write::ZstdEncoder},
+    Level,
+};
 use num::{
     complex::{Complex64, ComplexFloat},
     Complex,
 };
 use serde::{Deserialize, Serialize};
-use tokio::task::JoinHandle;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt, BufReader},
+    sync::{mpsc, Mutex},
+    task::JoinHandle,
+};
+
+mod animation;
+mod checkpoint;
+mod cluster;
+
+use animation::Animation;
 
 const CONFIG_FILE_PATH: &str = "config.toml";
 
+/// Number of points a single reusable buffer holds. Bounds peak memory to
+/// roughly `buffer pool size * CHUNK_SIZE` points, independent of grid size.
+const CHUNK_SIZE: usize = 5_000;
+
+/// Capacity of the channel workers use to hand finished chunks to the writer.
+/// Small on purpose: once it's full, workers block, applying backpressure.
+const WRITER_CHANNEL_CAPACITY: usize = 3;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
-struct Grid {
-    re_min: f64,
-    re_max: f64,
-    im_min: f64,
-    im_max: f64,
-    delta: f64,
+pub(crate) struct Grid {
+    pub(crate) re_min: f64,
+    pub(crate) re_max: f64,
+    pub(crate) im_min: f64,
+    pub(crate) im_max: f64,
+    pub(crate) delta: f64,
 }
 impl Default for Grid {
     fn default() -> Self {
@@ -35,17 +58,92 @@ impl Default for Grid {
 }
 
 #[derive(Clone, Debug)]
-struct Point {
-    position: Complex64,
-    value: f64,
+pub(crate) struct Point {
+    pub(crate) position: Complex64,
+    pub(crate) value: f64,
+}
+
+/// Flat, `bincode`-friendly shape of a [`Point`], used by the `Bincode` output
+/// format. Every field is a fixed-size `f64`, so each encoded record is a
+/// constant number of bytes, which is what lets [`read_bincode_export`] split
+/// the decompressed stream back into records without a length prefix.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct PointRecord {
+    pub(crate) re: f64,
+    pub(crate) im: f64,
+    pub(crate) value: f64,
+}
+
+impl From<&Point> for PointRecord {
+    fn from(point: &Point) -> Self {
+        PointRecord {
+            re: point.position.re(),
+            im: point.position.im(),
+            value: point.value,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub(crate) enum OutputFormat {
+    Csv,
+    /// `bincode`-encoded [`PointRecord`]s streamed through a zstd encoder.
+    /// `level` is the zstd compression level (see [`async_compression::Level::Precise`]).
+    Bincode {
+        level: i32,
+    },
+}
+
+impl Default for OutputFormat {
+    fn default() -> Self {
+        OutputFormat::Csv
+    }
+}
+
+/// How the grid gets computed: entirely on this machine, or split across a
+/// coordinator/worker pair talking over TCP (see the [`cluster`] module).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) enum RunMode {
+    Local,
+    /// Bind `bind_addr`, hand out chunks to connecting workers, and merge
+    /// their results. `expected_workers` only sizes the initial chunking;
+    /// more or fewer workers may actually connect.
+    Coordinator {
+        bind_addr: String,
+        expected_workers: usize,
+    },
+    /// Connect to `coordinator_addr` and compute whatever chunks it sends.
+    Worker {
+        coordinator_addr: String,
+    },
+    /// Read back a `Bincode` export at `path` and report how many points it
+    /// decoded, instead of rendering anything - exercises the same round
+    /// trip [`read_bincode_export`] claims to provide.
+    Verify {
+        path: String,
+    },
+}
+
+impl Default for RunMode {
+    fn default() -> Self {
+        RunMode::Local
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-struct Config {
-    grid: Grid,
-    iterations: usize,
-    bound: f64,
-    threads: usize,
+pub(crate) struct Config {
+    pub(crate) grid: Grid,
+    pub(crate) iterations: usize,
+    pub(crate) bound: f64,
+    pub(crate) threads: usize,
+    #[serde(default)]
+    pub(crate) output: OutputFormat,
+    #[serde(default)]
+    pub(crate) mode: RunMode,
+    /// When set, `RunMode::Local` renders a zoom sequence instead of a single
+    /// frame - see the [`animation`] module.
+    #[serde(default)]
+    pub(crate) animation: Option<Animation>,
 }
 
 impl Default for Config {
@@ -55,21 +153,72 @@ impl Default for Config {
             iterations: 200,
             bound: 2.0,
             threads: 1,
+            output: OutputFormat::default(),
+            mode: RunMode::default(),
+            animation: None,
         }
     }
 }
 
-fn mandelbrot_result(mut point: Point, config: &Config) -> Point {
+/// Generates every point of `grid` at its `delta` resolution, unsolved
+/// (`value` is a placeholder until [`mandelbrot_result`] fills it in).
+pub(crate) fn generate_grid_points(grid: &Grid) -> Vec<Point> {
+    let mut grid_data = vec![];
+
+    let mut x: f64 = grid.re_min;
+    let mut y: f64 = grid.im_min;
+    while x <= grid.re_max {
+        x += grid.delta;
+
+        while y <= grid.im_max {
+            y += grid.delta;
+            let point = Point {
+                position: Complex::new(x, y),
+                value: 0.0,
+            };
+
+            grid_data.push(point);
+        }
+
+        // reset y coordinate
+        y = grid.im_min;
+    }
+
+    grid_data
+}
+
+/// Counts how many points [`generate_grid_points`] would produce for `grid`,
+/// without materializing them - used to size chunks up front.
+pub(crate) fn count_grid_points(grid: &Grid) -> usize {
+    let mut count = 0usize;
+
+    let mut x: f64 = grid.re_min;
+    let mut y: f64 = grid.im_min;
+    while x <= grid.re_max {
+        x += grid.delta;
+
+        while y <= grid.im_max {
+            y += grid.delta;
+            count += 1;
+        }
+
+        y = grid.im_min;
+    }
+
+    count
+}
+
+pub(crate) fn mandelbrot_result(mut point: Point, iterations: usize, bound: f64) -> Point {
     let c = point.position;
     let mut z = Complex::new(0.0, 0.0);
-    for i in 0..config.iterations {
+    for i in 0..iterations {
         z = z * z + c;
-        if z.abs() > config.bound {
+        if z.abs() > bound {
             break;
         }
         //println!("c: {c}, z_{i}: {z}");
     }
-    if z.abs() > config.bound {
+    if z.abs() > bound {
         point.value = f64::NAN;
     } else {
         point.value = z.abs();
@@ -94,103 +243,280 @@ fn read_config_from_file() -> anyhow::Result<Config> {
     toml::from_str(&contents).map_err(anyhow::Error::new)
 }
 
+/// Drains finished chunks from `data_rx` and serializes them to `filename`
+/// incrementally, handing each buffer back to the pool via `return_tx` once
+/// it has been written so the sending worker can refill and reuse it. When
+/// `checkpoint` is set, every chunk is also appended to the sidecar, which is
+/// flushed before the pool gets its buffer back; the output file itself is
+/// only flushed once `data_rx` closes.
+pub(crate) async fn run_writer(
+    filename: String,
+    output: OutputFormat,
+    mut data_rx: mpsc::Receiver<(usize, Vec<Point>)>,
+    return_tx: mpsc::Sender<Vec<Point>>,
+    mut checkpoint: Option<checkpoint::CheckpointWriter>,
+) -> anyhow::Result<usize> {
+    let mut points_written = 0;
+
+    match output {
+        OutputFormat::Csv => {
+            let mut wtr = csv::Writer::from_path(filename)?;
+            wtr.write_record(["re", "im", "value"])?;
+
+            while let Some((chunk_index, mut chunk)) = data_rx.recv().await {
+                for p in chunk.iter() {
+                    wtr.serialize((p.position.re(), p.position.im(), p.value))?;
+                }
+                if let Some(writer) = checkpoint.as_mut() {
+                    let records: Vec<PointRecord> = chunk.iter().map(PointRecord::from).collect();
+                    writer.append(chunk_index, &records).await?;
+                }
+                points_written += chunk.len();
+
+                chunk.clear();
+                // buffer pool may be gone, or full (chunks replayed from a
+                // checkpoint add buffers the pool was never seeded with);
+                // the buffer is then dropped instead of blocking the writer
+                let _ = return_tx.try_send(chunk);
+            }
+            wtr.flush()?;
+        }
+        OutputFormat::Bincode { level } => {
+            let file = tokio::fs::File::create(filename).await?;
+            let mut encoder = ZstdEncoder::with_quality(file, Level::Precise(level));
+
+            while let Some((chunk_index, mut chunk)) = data_rx.recv().await {
+                for p in chunk.iter() {
+                    let record = PointRecord::from(p);
+                    encoder.write_all(&bincode::serialize(&record)?).await?;
+                }
+                if let Some(writer) = checkpoint.as_mut() {
+                    let records: Vec<PointRecord> = chunk.iter().map(PointRecord::from).collect();
+                    writer.append(chunk_index, &records).await?;
+                }
+                points_written += chunk.len();
+
+                chunk.clear();
+                let _ = return_tx.try_send(chunk);
+            }
+            encoder.shutdown().await?;
+        }
+    }
+
+    if let Some(writer) = checkpoint {
+        writer.finish().await?;
+    }
+
+    Ok(points_written)
+}
+
+/// Reads back a file written by the `Bincode` output format, undoing the
+/// zstd compression and splitting the decompressed bytes into [`PointRecord`]s.
+/// Every record bincode-encodes to the same number of bytes (three `f64`s,
+/// no variable-length fields), so the stream can be chunked without a
+/// length prefix. Reachable through `RunMode::Verify`.
+pub(crate) async fn read_bincode_export(path: &str) -> anyhow::Result<Vec<PointRecord>> {
+    let file = tokio::fs::File::open(path).await?;
+    let mut decoder = ZstdDecoder::new(BufReader::new(file));
+
+    let mut bytes = Vec::new();
+    decoder.read_to_end(&mut bytes).await?;
+
+    let record_size = bincode::serialized_size(&PointRecord {
+        re: 0.0,
+        im: 0.0,
+        value: 0.0,
+    })? as usize;
+
+    bytes
+        .chunks(record_size)
+        .map(|chunk| bincode::deserialize(chunk).map_err(anyhow::Error::new))
+        .collect()
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    let start = SystemTime::now();
-
-    let (progress_sender, progress_receiver) = channel::<usize>();
     let config = match read_config_from_file() {
         Ok(config) => config,
-        Err(e) => {
+        Err(_) => {
             let config = Config::default();
             write_config_to_file(&config)?;
             config
         }
     };
 
-    let mut grid_data: Vec<Point> = vec![];
+    match config.mode.clone() {
+        RunMode::Local => match config.animation.clone() {
+            Some(animation) => animation::run_animation(config, animation).await,
+            None => run_local(config).await,
+        },
+        RunMode::Coordinator {
+            bind_addr,
+            expected_workers,
+        } => cluster::run_coordinator(config, bind_addr, expected_workers).await,
+        RunMode::Worker { coordinator_addr } => cluster::run_worker(coordinator_addr).await,
+        RunMode::Verify { path } => {
+            let records = read_bincode_export(&path).await?;
+            println!("{path}: decoded {} points", records.len());
+            Ok(())
+        }
+    }
+}
 
-    // fill grid_data vector
+async fn run_local(config: Config) -> anyhow::Result<()> {
+    let start = SystemTime::now();
 
-    let mut x: f64 = config.grid.re_min;
-    let mut y: f64 = config.grid.im_min;
-    while x <= config.grid.re_max {
-        x += config.grid.delta;
+    let (progress_sender, progress_receiver) = channel::<usize>();
 
-        while y <= config.grid.im_max {
-            y += config.grid.delta;
-            //println!("x: {x}, y:{y}");
-            let mut point = Point {
-                position: Complex::new(x, y),
-                value: 0.0,
-            };
+    // Chunks are numbered by their position in the grid, not by which
+    // worker handles them, so a checkpoint stays valid even if `threads`
+    // changes between runs.
+    let checkpoint_path = checkpoint::checkpoint_path(&config)?;
+    let mut completed_chunks = checkpoint::load(&checkpoint_path).await?;
+    if !completed_chunks.is_empty() {
+        println!(
+            "resuming from checkpoint: {} chunk(s) already done",
+            completed_chunks.len()
+        );
+    }
 
-            grid_data.push(point);
-        }
+    // Chunks are sub-grids, not pre-generated points: workers call
+    // generate_grid_points on their own slice, so the full grid is never
+    // resident in memory at once - only the points generated for whichever
+    // chunks are in flight.
+    let total_points = count_grid_points(&config.grid);
+    let completed_points: usize = completed_chunks.values().map(Vec::len).sum();
+    let chunk_count = ((total_points + CHUNK_SIZE - 1) / CHUNK_SIZE).max(1);
 
-        // reset y coordinate
-        y = config.grid.im_min;
+    let pending: VecDeque<(usize, Grid)> = cluster::split_into_chunks(&config.grid, chunk_count)
+        .into_iter()
+        .enumerate()
+        .filter(|(chunk_index, _)| !completed_chunks.contains_key(chunk_index))
+        .collect();
+    let total_work_amount = total_points.saturating_sub(completed_points);
+    let pending = Arc::new(Mutex::new(pending));
+
+    // Writer task owns the output file, the checkpoint sidecar, and a small
+    // pool of reusable buffers. Workers pull an empty buffer from the pool,
+    // fill it with one chunk's worth of points, hand it to the writer, and
+    // get an emptied buffer back - keeping peak memory to a handful of
+    // chunks regardless of grid size.
+    let filename = match config.output {
+        OutputFormat::Csv => "mandelbrot.csv".to_string(),
+        OutputFormat::Bincode { .. } => "mandelbrot.bin.zst".to_string(),
+    };
+    let (data_tx, data_rx) = mpsc::channel::<(usize, Vec<Point>)>(WRITER_CHANNEL_CAPACITY);
+    let (return_tx, return_rx) =
+        mpsc::channel::<Vec<Point>>(config.threads + WRITER_CHANNEL_CAPACITY);
+
+    for _ in 0..(config.threads + WRITER_CHANNEL_CAPACITY) {
+        return_tx
+            .send(Vec::with_capacity(CHUNK_SIZE))
+            .await
+            .expect("seeding buffer pool");
     }
+    let return_rx = Arc::new(Mutex::new(return_rx));
 
-    //------------------------------
+    let checkpoint_writer = checkpoint::CheckpointWriter::create(&checkpoint_path).await?;
+    let writer_handle: JoinHandle<anyhow::Result<usize>> = tokio::spawn(run_writer(
+        filename,
+        config.output,
+        data_rx,
+        return_tx,
+        Some(checkpoint_writer),
+    ));
 
-    let total_work_amount = grid_data.len();
-    let mut join_handles: Vec<JoinHandle<Vec<Point>>> = vec![];
-    let grid_data_vecs: Vec<Vec<Point>> = grid_data
-        .chunks(grid_data.len() / config.threads)
-        .map(|s| s.into())
-        .collect();
+    // Chunks already known from a previous run are replayed straight into
+    // the output and the new checkpoint's tmp file, unchanged. The old
+    // checkpoint itself is untouched until CheckpointWriter::finish renames
+    // the tmp file over it, so a crash during replay can't lose it.
+    for (chunk_index, points) in completed_chunks.drain() {
+        let restored = points
+            .iter()
+            .map(|r| Point {
+                position: Complex::new(r.re, r.im),
+                value: r.value,
+            })
+            .collect();
+        data_tx.send((chunk_index, restored)).await?;
+    }
+
+    let mut join_handles: Vec<JoinHandle<()>> = vec![];
 
-    for v in grid_data_vecs {
+    for worker_id in 0..config.threads {
         let sender = progress_sender.clone();
         let cfg = config.clone();
+        let data_tx = data_tx.clone();
+        let return_rx = Arc::clone(&return_rx);
+        let pending = Arc::clone(&pending);
 
         let thread_join_handle = tokio::spawn(async move {
-            println!("Thread Started,  work:{}", v.len());
-            let mut points_done: Vec<Point> = vec![];
-            for point in v {
-                points_done.push(mandelbrot_result(point, &cfg));
-                if points_done.len() % 1000 == 0 {
-                    sender.send(1000).expect("Could send progress");
+            println!("Thread {worker_id} started");
+
+            loop {
+                let (chunk_index, grid) = match pending.lock().await.pop_front() {
+                    Some(item) => item,
+                    None => break,
+                };
+                let points = generate_grid_points(&grid);
+
+                let mut buffer = return_rx
+                    .lock()
+                    .await
+                    .recv()
+                    .await
+                    .expect("buffer pool closed while a worker still had work");
+                buffer.clear();
+
+                let mut progress_since_report = 0;
+                for point in points {
+                    buffer.push(mandelbrot_result(point, cfg.iterations, cfg.bound));
+                    progress_since_report += 1;
+                    if progress_since_report == 1000 {
+                        sender
+                            .send(progress_since_report)
+                            .expect("Could send progress");
+                        progress_since_report = 0;
+                    }
                 }
+                if progress_since_report > 0 {
+                    sender
+                        .send(progress_since_report)
+                        .expect("Could send progress");
+                }
+
+                data_tx
+                    .send((chunk_index, buffer))
+                    .await
+                    .expect("writer task gone");
             }
-            sender
-                .send(points_done.len() % 1000)
-                .expect("Could send progress");
-            points_done
         });
 
         join_handles.push(thread_join_handle);
     }
-    // manually drop the last progress sender that we cloned from
+    // manually drop the last progress sender and data sender that we cloned from
     drop(progress_sender);
+    drop(data_tx);
 
     let mut total_progress = 0;
 
     while let Ok(single_progress) = progress_receiver.recv() {
         total_progress += single_progress;
-        let progress_percentage = (total_progress as f64 / total_work_amount as f64) * 100_f64;
+        let progress_percentage =
+            (total_progress as f64 / total_work_amount.max(1) as f64) * 100_f64;
         print!("\r{progress_percentage:.2}%      ");
     }
 
     println!("---");
-    println!("collecting results");
-    let mut points_done_vec: Vec<Point> = Vec::new();
+    println!("waiting for workers to finish...");
 
     for join_handle in join_handles {
-        let points_done = join_handle.await.expect("could not join thread");
-        points_done_vec.extend(points_done);
+        join_handle.await.expect("could not join thread");
     }
 
-    println!("Exporting...");
+    let points_written = writer_handle.await.expect("could not join writer task")?;
 
-    let filename = "mandelbrot.csv".to_string();
-    let mut wtr = csv::Writer::from_path(filename)?;
-    wtr.write_record(vec!["re", "im", "value"])?;
-    for p in points_done_vec.iter() {
-        wtr.serialize((p.position.re(), p.position.im(), p.value))?;
-    }
-    wtr.flush()?;
+    println!("wrote {points_written} points");
     let end = SystemTime::now();
     let duration = end.duration_since(start)?;
     println!("took: {}ms", duration.as_millis());